@@ -0,0 +1,34 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_formula_engine::{EstimatorInputs, FormulaEngine};
+
+// Invariant: for arbitrary EstimatorInputs, the engine either returns a
+// descriptive error or an all-finite, non-negative result. It must never
+// panic, and it must never silently produce NaN/Inf/garbage.
+//
+// This calls `FormulaEngine::compute_scenario` rather than the `calculate`
+// wasm_bindgen entry point: `calculate` can only run on a `wasm32` target
+// because it constructs `JsValue` on its error path, while `cargo fuzz`
+// builds and runs this harness natively.
+fuzz_target!(|inputs: EstimatorInputs| {
+    if let Ok(results) = FormulaEngine::compute_scenario(&inputs) {
+        let results_json = serde_json::to_string(&results).expect("EstimatorResults must always serialize");
+        let results: serde_json::Value =
+            serde_json::from_str(&results_json).expect("serialized results must be valid JSON");
+
+        fn all_finite_non_negative(value: &serde_json::Value) -> bool {
+            match value {
+                serde_json::Value::Number(n) => n.as_f64().map_or(true, |f| f.is_finite() && f >= 0.0),
+                serde_json::Value::Array(items) => items.iter().all(all_finite_non_negative),
+                serde_json::Value::Object(fields) => fields.values().all(all_finite_non_negative),
+                _ => true,
+            }
+        }
+
+        assert!(
+            all_finite_non_negative(&results),
+            "compute_scenario() returned a non-finite or negative field: {results_json}"
+        );
+    }
+});
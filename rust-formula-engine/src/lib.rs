@@ -14,18 +14,26 @@
 use wasm_bindgen::prelude::*;
 use serde::{Deserialize, Serialize};
 
+// The console.log import only resolves on wasm32; a native fallback keeps
+// the crate buildable for native targets such as the `cargo fuzz` harness.
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     fn log(s: &str);
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+fn log(s: &str) {
+    eprintln!("{}", s);
+}
+
 macro_rules! console_log {
     ($($t:tt)*) => (log(&format_args!($($t)*).to_string()))
 }
 
 /**
- * Formula constants
+ * Formula constants (defaults used when a scenario does not supply a `Schedule`)
  */
 const INTEGRATION_FACTOR: f64 = 0.02;
 const EXTRA_FORM_FACTOR: f64 = 0.01;
@@ -38,11 +46,137 @@ const WORKING_DAYS_PER_MONTH: f64 = 20.0;
 const BASELINE_FORMS: i32 = 10;
 const MAX_PMO_ITERATIONS: usize = 10;
 const PMO_CONVERGENCE_THRESHOLD: f64 = 0.01;
+/// How close `rate·overlap/capacity` may get to 1 before we warn that the
+/// closed-form solve is approaching the non-convergent boundary.
+const PMO_DIVERGENCE_WARNING_THRESHOLD: f64 = 0.9;
+/// Upper bound accepted for `fte` — beyond this a payload is almost
+/// certainly malformed rather than a legitimate program size.
+const MAX_FTE: f64 = 100_000.0;
+/// Upper bound accepted for `overlap_factor`.
+const MAX_OVERLAP_FACTOR: f64 = 10.0;
+/// Coefficients (`sb`, `pc`, `os`) are clamped to this ceiling so a single
+/// extreme or adversarial input can't blow `e_ft` up to infinity through the
+/// `(1 + sb) * (1 + pc) * (1 + os)` product.
+const MAX_COEFFICIENT: f64 = 1_000.0;
+/// The formula generation used when `EstimatorInputs.formula_version` is omitted.
+const LATEST_FORMULA_VERSION: u32 = 1;
+/// Formula generations this build can dispatch to. Adding version 2 means
+/// adding it here and to `dispatch_calculate`'s match, not touching version 1.
+const SUPPORTED_FORMULA_VERSIONS: &[u32] = &[1];
+const DEFAULT_PHASE_WEIGHTS: [(&str, f64); 5] = [
+    ("Prepare", 0.10),
+    ("Explore", 0.15),
+    ("Realize", 0.50),
+    ("Deploy", 0.15),
+    ("Run", 0.10),
+];
+
+/**
+ * Schedule
+ *
+ * Runtime-configurable calibration profile for every weighting factor in the
+ * estimator. Callers that omit this from `EstimatorInputs` get the built-in
+ * defaults above, so existing payloads keep working unchanged.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Schedule {
+    pub integration_factor: f64,
+    pub extra_form_factor: f64,
+    pub fit_gap_factor: f64,
+    pub entity_factor: f64,
+    pub country_factor: f64,
+    pub language_factor: f64,
+    pub pmo_monthly_rate: f64,
+    pub working_days_per_month: f64,
+    pub baseline_forms: i32,
+    pub max_pmo_iterations: usize,
+    pub pmo_convergence_threshold: f64,
+    pub phase_weights: Vec<(String, f64)>,
+    /// Use the old fixed-point iteration instead of the closed-form PMO solve
+    /// (kept for backward-compatible comparison against historical results).
+    #[serde(default)]
+    pub use_iterative_pmo: bool,
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule {
+            integration_factor: INTEGRATION_FACTOR,
+            extra_form_factor: EXTRA_FORM_FACTOR,
+            fit_gap_factor: FIT_GAP_FACTOR,
+            entity_factor: ENTITY_FACTOR,
+            country_factor: COUNTRY_FACTOR,
+            language_factor: LANGUAGE_FACTOR,
+            pmo_monthly_rate: PMO_MONTHLY_RATE,
+            working_days_per_month: WORKING_DAYS_PER_MONTH,
+            baseline_forms: BASELINE_FORMS,
+            max_pmo_iterations: MAX_PMO_ITERATIONS,
+            pmo_convergence_threshold: PMO_CONVERGENCE_THRESHOLD,
+            phase_weights: DEFAULT_PHASE_WEIGHTS
+                .iter()
+                .map(|(name, weight)| (name.to_string(), *weight))
+                .collect(),
+            use_iterative_pmo: false,
+        }
+    }
+}
+
+impl Schedule {
+    /**
+     * Validate that every factor is finite and non-negative, `working_days_per_month`
+     * is positive, `baseline_forms` and each phase weight are non-negative, and the
+     * phase weights sum to 1.0 (within floating-point tolerance)
+     */
+    fn validate(&self) -> Result<(), String> {
+        let factors = [
+            ("integration_factor", self.integration_factor),
+            ("extra_form_factor", self.extra_form_factor),
+            ("fit_gap_factor", self.fit_gap_factor),
+            ("entity_factor", self.entity_factor),
+            ("country_factor", self.country_factor),
+            ("language_factor", self.language_factor),
+            ("pmo_monthly_rate", self.pmo_monthly_rate),
+            ("working_days_per_month", self.working_days_per_month),
+            ("pmo_convergence_threshold", self.pmo_convergence_threshold),
+        ];
+
+        for (name, value) in factors {
+            if !value.is_finite() {
+                return Err(format!("Schedule.{} must be a finite number, got {}", name, value));
+            }
+            if value < 0.0 {
+                return Err(format!("Schedule.{} must be non-negative, got {}", name, value));
+            }
+        }
+
+        if self.working_days_per_month <= 0.0 {
+            return Err("Schedule.working_days_per_month must be positive".to_string());
+        }
+
+        if self.baseline_forms < 0 {
+            return Err(format!("Schedule.baseline_forms must be non-negative, got {}", self.baseline_forms));
+        }
+
+        if let Some((name, weight)) = self.phase_weights.iter().find(|(_, weight)| *weight < 0.0) {
+            return Err(format!("Schedule phase weight '{}' must be non-negative, got {}", name, weight));
+        }
+
+        let total: f64 = self.phase_weights.iter().map(|(_, weight)| weight).sum();
+
+        if !total.is_finite() || (total - 1.0).abs() > 1e-6 {
+            return Err(format!("Schedule phase_weights must sum to 1.0, got {:.6}", total));
+        }
+
+        Ok(())
+    }
+}
 
 /**
  * L3 Scope Item
  */
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct L3ScopeItem {
     pub l3_code: String,
     pub coefficient: f64,
@@ -53,6 +187,7 @@ pub struct L3ScopeItem {
  * Profile Configuration
  */
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Profile {
     pub name: String,
     pub base_ft: f64,
@@ -64,6 +199,7 @@ pub struct Profile {
  * Estimator Inputs
  */
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct EstimatorInputs {
     pub selected_l3_items: Vec<L3ScopeItem>,
     pub integrations: i32,
@@ -76,6 +212,12 @@ pub struct EstimatorInputs {
     pub fte: f64,
     pub utilization: f64,
     pub overlap_factor: f64,
+    #[serde(default)]
+    pub schedule: Option<Schedule>,
+    /// Selects which formula generation computes this scenario; defaults to
+    /// `LATEST_FORMULA_VERSION` so existing payloads keep working unchanged.
+    #[serde(default)]
+    pub formula_version: Option<u32>,
 }
 
 /**
@@ -98,6 +240,60 @@ pub struct Coefficients {
     pub os: f64,
 }
 
+/**
+ * How much a single selected L3 item contributed to Scope Breadth
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct L3Contribution {
+    pub l3_code: String,
+    pub coefficient: f64,
+}
+
+/**
+ * Breakdown of Scope Breadth (Sb): which L3 items contributed, their summed
+ * coefficient, and the integration contribution.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopeBreadthBreakdown {
+    pub l3_contributions: Vec<L3Contribution>,
+    pub integration_contribution: f64,
+    pub total: f64,
+}
+
+/**
+ * Breakdown of Process Complexity (Pc): the forms-vs-fit split.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessComplexityBreakdown {
+    pub extra_forms: i32,
+    pub forms_contribution: f64,
+    pub fit_gap: f64,
+    pub fit_gap_contribution: f64,
+    pub total: f64,
+}
+
+/**
+ * Breakdown of Organizational Scale (Os): the entity/country/language split.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrgScaleBreakdown {
+    pub entity_contribution: f64,
+    pub country_contribution: f64,
+    pub language_contribution: f64,
+    pub total: f64,
+}
+
+/**
+ * Full coefficient breakdown, so a UI can explain "why this number" without
+ * re-implementing the formula.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoefficientBreakdown {
+    pub scope_breadth: ScopeBreadthBreakdown,
+    pub process_complexity: ProcessComplexityBreakdown,
+    pub org_scale: OrgScaleBreakdown,
+}
+
 /**
  * Intermediate Values
  */
@@ -120,6 +316,22 @@ pub struct EstimatorResults {
     pub capacity_per_month: f64,
     pub coefficients: Coefficients,
     pub intermediate_values: IntermediateValues,
+    pub breakdown: CoefficientBreakdown,
+    /// The formula generation that produced this result, so historical
+    /// quotes stay reproducible even as `LATEST_FORMULA_VERSION` advances.
+    pub formula_version: u32,
+}
+
+/**
+ * Per-scenario outcome of a batch calculation, tagged with its original
+ * index so failures are attributable instead of silently dropped.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchResultEntry {
+    pub index: usize,
+    pub success: bool,
+    pub result: Option<EstimatorResults>,
+    pub error: Option<String>,
 }
 
 /**
@@ -139,40 +351,307 @@ impl FormulaEngine {
     /**
      * Calculate Scope Breadth (Sb)
      */
-    fn calculate_scope_breadth(selected_items: &[L3ScopeItem], integrations: i32) -> f64 {
-        let item_coefficients: f64 = selected_items
+    fn calculate_scope_breadth(
+        selected_items: &[L3ScopeItem],
+        integrations: i32,
+        schedule: &Schedule,
+    ) -> ScopeBreadthBreakdown {
+        let l3_contributions: Vec<L3Contribution> = selected_items
             .iter()
             .filter(|item| item.default_tier != "D")
-            .map(|item| item.coefficient)
-            .sum();
+            .map(|item| L3Contribution {
+                l3_code: item.l3_code.clone(),
+                coefficient: item.coefficient,
+            })
+            .collect();
 
-        let integration_factor = (integrations as f64) * INTEGRATION_FACTOR;
+        let item_coefficients: f64 = l3_contributions.iter().map(|c| c.coefficient).sum();
+        let integration_contribution = (integrations as f64) * schedule.integration_factor;
+        let total = (item_coefficients + integration_contribution).clamp(0.0, MAX_COEFFICIENT);
 
-        f64::max(0.0, item_coefficients + integration_factor)
+        ScopeBreadthBreakdown {
+            l3_contributions,
+            integration_contribution,
+            total,
+        }
     }
 
     /**
      * Calculate Process Complexity (Pc)
      */
-    fn calculate_process_complexity(custom_forms: i32, fit_to_standard: f64) -> f64 {
-        let extra_forms = i32::max(0, custom_forms - BASELINE_FORMS);
-        let forms_factor = (extra_forms as f64) * EXTRA_FORM_FACTOR;
+    fn calculate_process_complexity(
+        custom_forms: i32,
+        fit_to_standard: f64,
+        schedule: &Schedule,
+    ) -> ProcessComplexityBreakdown {
+        let extra_forms = i32::max(0, custom_forms - schedule.baseline_forms);
+        let forms_contribution = (extra_forms as f64) * schedule.extra_form_factor;
 
         let fit_gap = f64::max(0.0, 1.0 - fit_to_standard);
-        let fit_factor = fit_gap * FIT_GAP_FACTOR;
+        let fit_gap_contribution = fit_gap * schedule.fit_gap_factor;
+
+        let total = (forms_contribution + fit_gap_contribution).clamp(0.0, MAX_COEFFICIENT);
 
-        f64::max(0.0, forms_factor + fit_factor)
+        ProcessComplexityBreakdown {
+            extra_forms,
+            forms_contribution,
+            fit_gap,
+            fit_gap_contribution,
+            total,
+        }
     }
 
     /**
      * Calculate Organizational Scale (Os)
      */
-    fn calculate_org_scale(legal_entities: i32, countries: i32, languages: i32) -> f64 {
-        let entities_factor = f64::max(0.0, (legal_entities - 1) as f64) * ENTITY_FACTOR;
-        let countries_factor = f64::max(0.0, (countries - 1) as f64) * COUNTRY_FACTOR;
-        let languages_factor = f64::max(0.0, (languages - 1) as f64) * LANGUAGE_FACTOR;
+    fn calculate_org_scale(legal_entities: i32, countries: i32, languages: i32, schedule: &Schedule) -> OrgScaleBreakdown {
+        let entity_contribution = f64::max(0.0, (legal_entities - 1) as f64) * schedule.entity_factor;
+        let country_contribution = f64::max(0.0, (countries - 1) as f64) * schedule.country_factor;
+        let language_contribution = f64::max(0.0, (languages - 1) as f64) * schedule.language_factor;
+
+        let total =
+            (entity_contribution + country_contribution + language_contribution).clamp(0.0, MAX_COEFFICIENT);
 
-        f64::max(0.0, entities_factor + countries_factor + languages_factor)
+        OrgScaleBreakdown {
+            entity_contribution,
+            country_contribution,
+            language_contribution,
+            total,
+        }
+    }
+
+    /**
+     * Reject non-finite or out-of-range numeric inputs up front so they can
+     * never propagate into `e_ft`/`e_pmo`/serialized results as NaN/Inf.
+     */
+    fn validate_inputs(inputs: &EstimatorInputs) -> Result<(), String> {
+        let finite_fields = [
+            ("fte", inputs.fte),
+            ("utilization", inputs.utilization),
+            ("overlap_factor", inputs.overlap_factor),
+            ("fit_to_standard", inputs.fit_to_standard),
+            ("profile.base_ft", inputs.profile.base_ft),
+            ("profile.basis", inputs.profile.basis),
+            ("profile.security_auth", inputs.profile.security_auth),
+        ];
+
+        for (name, value) in finite_fields {
+            if !value.is_finite() {
+                return Err(format!("{} must be a finite number, got {}", name, value));
+            }
+        }
+
+        if !(0.0..=MAX_FTE).contains(&inputs.fte) {
+            return Err(format!("fte must be within [0.0, {}], got {}", MAX_FTE, inputs.fte));
+        }
+
+        if !(0.0..=1.0).contains(&inputs.utilization) {
+            return Err(format!(
+                "utilization must be within [0.0, 1.0], got {}",
+                inputs.utilization
+            ));
+        }
+
+        if !(0.0..=MAX_OVERLAP_FACTOR).contains(&inputs.overlap_factor) {
+            return Err(format!(
+                "overlap_factor must be within [0.0, {}], got {}",
+                MAX_OVERLAP_FACTOR, inputs.overlap_factor
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&inputs.fit_to_standard) {
+            return Err(format!(
+                "fit_to_standard must be within [0.0, 1.0], got {}",
+                inputs.fit_to_standard
+            ));
+        }
+
+        let non_negative_fields = [
+            ("profile.base_ft", inputs.profile.base_ft),
+            ("profile.basis", inputs.profile.basis),
+            ("profile.security_auth", inputs.profile.security_auth),
+        ];
+
+        for (name, value) in non_negative_fields {
+            if value < 0.0 {
+                return Err(format!("{} must be non-negative, got {}", name, value));
+            }
+        }
+
+        let count_fields = [
+            ("integrations", inputs.integrations, 0),
+            ("custom_forms", inputs.custom_forms, 0),
+            ("legal_entities", inputs.legal_entities, 1),
+            ("countries", inputs.countries, 1),
+            ("languages", inputs.languages, 1),
+        ];
+
+        for (name, value, min) in count_fields {
+            if value < min {
+                return Err(format!("{} must be at least {}, got {}", name, min, value));
+            }
+        }
+
+        for item in &inputs.selected_l3_items {
+            if !item.coefficient.is_finite() {
+                return Err(format!(
+                    "L3 item '{}' coefficient must be a finite number, got {}",
+                    item.l3_code, item.coefficient
+                ));
+            }
+            if item.coefficient < 0.0 {
+                return Err(format!("L3 item '{}' coefficient must be non-negative, got {}", item.l3_code, item.coefficient));
+            }
+        }
+
+        Ok(())
+    }
+
+    /**
+     * Guarantee every field of a success result is finite, as a last line of
+     * defense beyond `validate_inputs` and the coefficient clamps.
+     */
+    fn results_finite(results: &EstimatorResults) -> bool {
+        results.total_md.is_finite()
+            && results.duration_months.is_finite()
+            && results.pmo_md.is_finite()
+            && results.capacity_per_month.is_finite()
+            && results.coefficients.sb.is_finite()
+            && results.coefficients.pc.is_finite()
+            && results.coefficients.os.is_finite()
+            && results.intermediate_values.e_ft.is_finite()
+            && results.intermediate_values.e_fixed.is_finite()
+            && results.intermediate_values.d_raw.is_finite()
+            && results
+                .phases
+                .iter()
+                .all(|p| p.effort_md.is_finite() && p.duration_months.is_finite())
+            && results.breakdown.scope_breadth.total.is_finite()
+            && results.breakdown.scope_breadth.integration_contribution.is_finite()
+            && results
+                .breakdown
+                .scope_breadth
+                .l3_contributions
+                .iter()
+                .all(|c| c.coefficient.is_finite())
+            && results.breakdown.process_complexity.total.is_finite()
+            && results.breakdown.process_complexity.forms_contribution.is_finite()
+            && results.breakdown.process_complexity.fit_gap_contribution.is_finite()
+            && results.breakdown.org_scale.total.is_finite()
+            && results.breakdown.org_scale.entity_contribution.is_finite()
+            && results.breakdown.org_scale.country_contribution.is_finite()
+            && results.breakdown.org_scale.language_contribution.is_finite()
+    }
+
+    /**
+     * Solve the PMO fixed-point `d = ((B + d·R)/C)·O` for `d` (and the
+     * resulting PMO effort `d·R`).
+     *
+     * This is a linear fixed point with closed-form solution
+     * `d = (B·O/C) / (1 − R·O/C)`, so it replaces the old `MAX_PMO_ITERATIONS`
+     * loop with a single division. When `R·O/C ≥ 1` the series diverges (PMO
+     * cost grows faster than capacity absorbs it), so that case is rejected
+     * up front. `schedule.use_iterative_pmo` keeps the old loop available for
+     * backward-compatible comparison against historical results.
+     */
+    fn solve_pmo(
+        base_effort: f64,
+        capacity: f64,
+        overlap_factor: f64,
+        schedule: &Schedule,
+        verbose: bool,
+    ) -> Result<(f64, f64), String> {
+        if schedule.use_iterative_pmo {
+            let mut d = (base_effort / capacity) * overlap_factor;
+            let mut e_pmo = 0.0;
+
+            for i in 0..schedule.max_pmo_iterations {
+                let d_prev = d;
+                e_pmo = d * schedule.pmo_monthly_rate;
+                d = ((base_effort + e_pmo) / capacity) * overlap_factor;
+
+                if (d - d_prev).abs() < schedule.pmo_convergence_threshold {
+                    if verbose {
+                        console_log!("[Rust] ✅ PMO converged in {} iterations", i + 1);
+                    }
+                    break;
+                }
+            }
+
+            return Ok((d, e_pmo));
+        }
+
+        let k = schedule.pmo_monthly_rate * overlap_factor / capacity;
+
+        if k >= 1.0 {
+            return Err("PMO cost model does not converge (rate·overlap/capacity ≥ 1)".to_string());
+        }
+
+        if k > PMO_DIVERGENCE_WARNING_THRESHOLD {
+            console_log!(
+                "[Rust] ⚠️ PMO cost model is near the divergence boundary (rate·overlap/capacity = {:.4})",
+                k
+            );
+        }
+
+        let d_raw = (base_effort / capacity) * overlap_factor;
+        let d = d_raw / (1.0 - k);
+        let e_pmo = d * schedule.pmo_monthly_rate;
+
+        Ok((d, e_pmo))
+    }
+
+    /**
+     * Self-describing schema for `EstimatorInputs`/`EstimatorResults`, so a
+     * front-end doesn't have to hardcode field names/units/ranges or
+     * re-implement what `sb`/`pc`/`os` mean.
+     */
+    #[wasm_bindgen]
+    pub fn metadata(&self) -> Result<String, JsValue> {
+        let doc = serde_json::json!({
+            "formula_version": LATEST_FORMULA_VERSION,
+            "supported_formula_versions": SUPPORTED_FORMULA_VERSIONS,
+            "inputs": {
+                "selected_l3_items": { "type": "array", "item": {
+                    "l3_code": { "type": "string" },
+                    "coefficient": { "type": "number", "unit": "Sb points" },
+                    "default_tier": { "type": "string", "description": "'D' items are excluded from Sb" },
+                }},
+                "integrations": { "type": "integer", "unit": "count", "min": 0 },
+                "custom_forms": { "type": "integer", "unit": "count", "min": 0 },
+                "fit_to_standard": { "type": "number", "unit": "ratio", "min": 0.0, "max": 1.0 },
+                "legal_entities": { "type": "integer", "unit": "count", "min": 1 },
+                "countries": { "type": "integer", "unit": "count", "min": 1 },
+                "languages": { "type": "integer", "unit": "count", "min": 1 },
+                "profile": { "type": "object", "fields": {
+                    "name": { "type": "string" },
+                    "base_ft": { "type": "number", "unit": "MD" },
+                    "basis": { "type": "number", "unit": "MD" },
+                    "security_auth": { "type": "number", "unit": "MD" },
+                }},
+                "fte": { "type": "number", "unit": "FTE", "min": 0.0, "max": MAX_FTE },
+                "utilization": { "type": "number", "unit": "ratio", "min": 0.0, "max": 1.0 },
+                "overlap_factor": { "type": "number", "unit": "ratio", "min": 0.0, "max": MAX_OVERLAP_FACTOR },
+                "schedule": { "type": "object", "optional": true, "description": "runtime calibration profile; defaults to the built-in constants when omitted" },
+                "formula_version": { "type": "integer", "optional": true, "description": "selects the formula generation; defaults to the latest, see supported_versions()" },
+            },
+            "outputs": {
+                "total_md": { "type": "number", "unit": "MD" },
+                "duration_months": { "type": "number", "unit": "months" },
+                "pmo_md": { "type": "number", "unit": "MD" },
+                "phases": { "type": "array", "description": "effort/duration distributed across SAP Activate phases" },
+                "capacity_per_month": { "type": "number", "unit": "MD/month" },
+                "coefficients": {
+                    "sb": { "type": "number", "description": "Scope Breadth: sum of selected non-'D' L3 coefficients plus an integration factor" },
+                    "pc": { "type": "number", "description": "Process Complexity: extra custom forms above the baseline plus the fit-to-standard gap" },
+                    "os": { "type": "number", "description": "Organizational Scale: legal entity, country, and language multipliers" },
+                },
+                "breakdown": { "type": "object", "description": format!("per-factor contributions to sb/pc/os; each total is the sum of its contributions clamped to [0, {}], for an auditable 'why this number' UI", MAX_COEFFICIENT) },
+                "formula_version": { "type": "integer", "description": "the formula generation that produced this result, see supported_versions()" },
+            },
+        });
+
+        serde_json::to_string(&doc).map_err(|e| JsValue::from_str(&format!("Failed to serialize metadata: {}", e)))
     }
 
     /**
@@ -186,43 +665,88 @@ impl FormulaEngine {
         let inputs: EstimatorInputs = serde_json::from_str(inputs_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse inputs: {}", e)))?;
 
-        // Step 1: Calculate coefficients
-        let sb = Self::calculate_scope_breadth(&inputs.selected_l3_items, inputs.integrations);
-        let pc = Self::calculate_process_complexity(inputs.custom_forms, inputs.fit_to_standard);
-        let os = Self::calculate_org_scale(inputs.legal_entities, inputs.countries, inputs.languages);
+        let results = Self::dispatch_calculate(&inputs, true).map_err(|e| JsValue::from_str(&e))?;
 
-        // Step 2: Calculate functional/technical effort
-        let e_ft = inputs.profile.base_ft * (1.0 + sb) * (1.0 + pc) * (1.0 + os);
+        // Serialize results
+        let results_json = serde_json::to_string(&results)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize results: {}", e)))?;
 
-        // Step 3: Calculate fixed effort
-        let e_fixed = inputs.profile.basis + inputs.profile.security_auth;
+        console_log!(
+            "[Rust] ✅ Calculation complete: {:.2} MD, {:.2} months",
+            results.total_md,
+            results.duration_months
+        );
+
+        Ok(results_json)
+    }
+
+    /**
+     * Distribute effort across SAP Activate phases
+     */
+    fn distribute_phases(total_md: f64, total_duration: f64, schedule: &Schedule) -> Vec<PhaseBreakdown> {
+        schedule
+            .phase_weights
+            .iter()
+            .map(|(name, weight)| PhaseBreakdown {
+                phase_name: name.clone(),
+                effort_md: total_md * weight,
+                duration_months: total_duration * weight,
+            })
+            .collect()
+    }
 
-        // Step 4: Calculate capacity
-        let capacity = inputs.fte * WORKING_DAYS_PER_MONTH * inputs.utilization;
+    /**
+     * Resolve `inputs.formula_version` (defaulting to the latest) and
+     * dispatch to the matching implementation. Adding a new formula
+     * generation means adding a match arm here, not changing this signature
+     * or any existing arm.
+     */
+    fn dispatch_calculate(inputs: &EstimatorInputs, verbose: bool) -> Result<EstimatorResults, String> {
+        let version = inputs.formula_version.unwrap_or(LATEST_FORMULA_VERSION);
 
-        if capacity <= 0.0 {
-            return Err(JsValue::from_str("Capacity must be positive"));
+        if !SUPPORTED_FORMULA_VERSIONS.contains(&version) {
+            return Err(format!(
+                "Unsupported formula_version {}, supported versions are {:?}",
+                version, SUPPORTED_FORMULA_VERSIONS
+            ));
         }
 
-        // Step 5: Iterative PMO calculation
-        let mut d = ((e_ft + e_fixed) / capacity) * inputs.overlap_factor;
-        let mut e_pmo = 0.0;
+        match version {
+            1 => Self::calculate_v1(inputs, verbose),
+            _ => unreachable!("formula_version {} passed SUPPORTED_FORMULA_VERSIONS but has no match arm", version),
+        }
+    }
 
-        for i in 0..MAX_PMO_ITERATIONS {
-            let d_prev = d;
-            e_pmo = d * PMO_MONTHLY_RATE;
-            d = ((e_ft + e_fixed + e_pmo) / capacity) * inputs.overlap_factor;
+    /**
+     * Formula generation 1: the original scope/process/org coefficients and
+     * linear PMO cost model. Pure/side-effect-free (aside from the
+     * `console_log!` warning inside `solve_pmo`) so it parallelizes cleanly
+     * across `calculate_batch` / `calculate_batch_parallel`.
+     */
+    fn calculate_v1(inputs: &EstimatorInputs, verbose: bool) -> Result<EstimatorResults, String> {
+        let schedule = inputs.schedule.clone().unwrap_or_default();
+        schedule.validate()?;
+        Self::validate_inputs(inputs)?;
 
-            if (d - d_prev).abs() < PMO_CONVERGENCE_THRESHOLD {
-                console_log!("[Rust] ✅ PMO converged in {} iterations", i + 1);
-                break;
-            }
+        let scope_breadth = Self::calculate_scope_breadth(&inputs.selected_l3_items, inputs.integrations, &schedule);
+        let process_complexity =
+            Self::calculate_process_complexity(inputs.custom_forms, inputs.fit_to_standard, &schedule);
+        let org_scale =
+            Self::calculate_org_scale(inputs.legal_entities, inputs.countries, inputs.languages, &schedule);
+        let (sb, pc, os) = (scope_breadth.total, process_complexity.total, org_scale.total);
+
+        let e_ft = inputs.profile.base_ft * (1.0 + sb) * (1.0 + pc) * (1.0 + os);
+        let e_fixed = inputs.profile.basis + inputs.profile.security_auth;
+        let capacity = inputs.fte * schedule.working_days_per_month * inputs.utilization;
+
+        if capacity <= 0.0 {
+            return Err("Capacity must be positive".to_string());
         }
 
-        let e_total = e_ft + e_fixed + e_pmo;
+        let (d, e_pmo) = Self::solve_pmo(e_ft + e_fixed, capacity, inputs.overlap_factor, &schedule, verbose)?;
 
-        // Step 6: Distribute across phases
-        let phases = Self::distribute_phases(e_total, d);
+        let e_total = e_ft + e_fixed + e_pmo;
+        let phases = Self::distribute_phases(e_total, d, &schedule);
 
         let results = EstimatorResults {
             total_md: e_total,
@@ -236,110 +760,388 @@ impl FormulaEngine {
                 e_fixed,
                 d_raw: (e_ft + e_fixed) / capacity,
             },
+            breakdown: CoefficientBreakdown {
+                scope_breadth,
+                process_complexity,
+                org_scale,
+            },
+            formula_version: 1,
         };
 
-        // Serialize results
-        let results_json = serde_json::to_string(&results)
-            .map_err(|e| JsValue::from_str(&format!("Failed to serialize results: {}", e)))?;
-
-        console_log!("[Rust] ✅ Calculation complete: {:.2} MD, {:.2} months", e_total, d);
+        if !Self::results_finite(&results) {
+            return Err("Calculation produced a non-finite result".to_string());
+        }
 
-        Ok(results_json)
+        Ok(results)
     }
 
     /**
-     * Distribute effort across SAP Activate phases
+     * Formula generations this build understands, for client negotiation.
      */
-    fn distribute_phases(total_md: f64, total_duration: f64) -> Vec<PhaseBreakdown> {
-        let phase_weights = vec![
-            ("Prepare", 0.10),
-            ("Explore", 0.15),
-            ("Realize", 0.50),
-            ("Deploy", 0.15),
-            ("Run", 0.10),
-        ];
-
-        phase_weights
-            .into_iter()
-            .map(|(name, weight)| PhaseBreakdown {
-                phase_name: name.to_string(),
-                effort_md: total_md * weight,
-                duration_months: total_duration * weight,
-            })
-            .collect()
+    #[wasm_bindgen]
+    pub fn supported_versions(&self) -> Vec<u32> {
+        SUPPORTED_FORMULA_VERSIONS.to_vec()
     }
 
     /**
-     * Batch calculation for multiple scenarios (parallel processing)
+     * Batch calculation for multiple scenarios (serial)
+     *
+     * Each scenario's outcome is reported as a `BatchResultEntry` tagged with
+     * its original index, so a failing scenario (non-positive capacity,
+     * non-convergent PMO, bad schedule) is visible to the caller instead of
+     * being dropped from the output.
      */
     #[wasm_bindgen]
     pub fn calculate_batch(&self, inputs_array_json: &str) -> Result<String, JsValue> {
         console_log!("[Rust] 🔄 Starting batch calculation...");
 
-        // Parse array of inputs
         let inputs_array: Vec<EstimatorInputs> = serde_json::from_str(inputs_array_json)
             .map_err(|e| JsValue::from_str(&format!("Failed to parse inputs array: {}", e)))?;
 
-        // Process each input
-        let results: Vec<EstimatorResults> = inputs_array
-            .iter()
-            .filter_map(|inputs| {
-                // Manually process each input (parallel processing would require rayon)
-                let sb = Self::calculate_scope_breadth(&inputs.selected_l3_items, inputs.integrations);
-                let pc = Self::calculate_process_complexity(inputs.custom_forms, inputs.fit_to_standard);
-                let os = Self::calculate_org_scale(inputs.legal_entities, inputs.countries, inputs.languages);
-
-                let e_ft = inputs.profile.base_ft * (1.0 + sb) * (1.0 + pc) * (1.0 + os);
-                let e_fixed = inputs.profile.basis + inputs.profile.security_auth;
-                let capacity = inputs.fte * WORKING_DAYS_PER_MONTH * inputs.utilization;
-
-                if capacity <= 0.0 {
-                    return None;
-                }
+        let entries = Self::compute_batch_entries(&inputs_array);
 
-                let mut d = ((e_ft + e_fixed) / capacity) * inputs.overlap_factor;
-                let mut e_pmo = 0.0;
+        let results_json = serde_json::to_string(&entries)
+            .map_err(|e| JsValue::from_str(&format!("Failed to serialize results: {}", e)))?;
 
-                for _ in 0..MAX_PMO_ITERATIONS {
-                    let d_prev = d;
-                    e_pmo = d * PMO_MONTHLY_RATE;
-                    d = ((e_ft + e_fixed + e_pmo) / capacity) * inputs.overlap_factor;
+        console_log!("[Rust] ✅ Batch calculation complete: {} scenarios", entries.len());
 
-                    if (d - d_prev).abs() < PMO_CONVERGENCE_THRESHOLD {
-                        break;
-                    }
-                }
+        Ok(results_json)
+    }
 
-                let e_total = e_ft + e_fixed + e_pmo;
-                let phases = Self::distribute_phases(e_total, d);
-
-                Some(EstimatorResults {
-                    total_md: e_total,
-                    duration_months: d,
-                    pmo_md: e_pmo,
-                    phases,
-                    capacity_per_month: capacity,
-                    coefficients: Coefficients { sb, pc, os },
-                    intermediate_values: IntermediateValues {
-                        e_ft,
-                        e_fixed,
-                        d_raw: (e_ft + e_fixed) / capacity,
-                    },
-                })
-            })
-            .collect();
+    /**
+     * Batch calculation for multiple scenarios (parallel)
+     *
+     * Uses rayon's `par_iter` when compiled with the `parallel` feature for a
+     * threaded target (wasm with shared-memory/atomics, or native); otherwise
+     * falls back to the same serial path as `calculate_batch`.
+     */
+    #[wasm_bindgen]
+    pub fn calculate_batch_parallel(&self, inputs_array_json: &str) -> Result<String, JsValue> {
+        console_log!("[Rust] 🔄 Starting parallel batch calculation...");
 
-        // Serialize results
-        let results_json = serde_json::to_string(&results)
+        let inputs_array: Vec<EstimatorInputs> = serde_json::from_str(inputs_array_json)
+            .map_err(|e| JsValue::from_str(&format!("Failed to parse inputs array: {}", e)))?;
+
+        #[cfg(feature = "parallel")]
+        let entries: Vec<BatchResultEntry> = {
+            use rayon::prelude::*;
+            inputs_array
+                .par_iter()
+                .enumerate()
+                .map(|(index, inputs)| Self::compute_batch_entry(index, inputs))
+                .collect()
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let entries = Self::compute_batch_entries(&inputs_array);
+
+        let results_json = serde_json::to_string(&entries)
             .map_err(|e| JsValue::from_str(&format!("Failed to serialize results: {}", e)))?;
 
-        console_log!("[Rust] ✅ Batch calculation complete: {} scenarios", results.len());
+        console_log!("[Rust] ✅ Parallel batch calculation complete: {} scenarios", entries.len());
 
         Ok(results_json)
     }
+
+    fn compute_batch_entry(index: usize, inputs: &EstimatorInputs) -> BatchResultEntry {
+        match Self::compute_scenario(inputs) {
+            Ok(result) => BatchResultEntry {
+                index,
+                success: true,
+                result: Some(result),
+                error: None,
+            },
+            Err(e) => BatchResultEntry {
+                index,
+                success: false,
+                result: None,
+                error: Some(e),
+            },
+        }
+    }
+
+    fn compute_batch_entries(inputs_array: &[EstimatorInputs]) -> Vec<BatchResultEntry> {
+        inputs_array
+            .iter()
+            .enumerate()
+            .map(|(index, inputs)| Self::compute_batch_entry(index, inputs))
+            .collect()
+    }
+}
+
+// Kept outside the `#[wasm_bindgen] impl` above: wasm_bindgen generates JS
+// bindings for every `pub` item in that block, which requires return types
+// it knows how to convert across the ABI boundary, and `Result<_, String>`
+// doesn't qualify.
+impl FormulaEngine {
+    /**
+     * Compute a single scenario for batch processing. Thin wrapper over
+     * `dispatch_calculate` kept as its own name since `calculate_batch` /
+     * `calculate_batch_parallel` call it per-item.
+     *
+     * Also the entry point native test/fuzz harnesses should call directly:
+     * unlike `calculate`, it never touches `JsValue`, which `wasm_bindgen`
+     * can only construct on a `wasm32` target.
+     */
+    pub fn compute_scenario(inputs: &EstimatorInputs) -> Result<EstimatorResults, String> {
+        Self::dispatch_calculate(inputs, false)
+    }
 }
 
 #[wasm_bindgen(start)]
 pub fn main() {
     console_log!("[Rust] 🚀 WASM module loaded");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_inputs() -> EstimatorInputs {
+        EstimatorInputs {
+            selected_l3_items: vec![L3ScopeItem {
+                l3_code: "X1".to_string(),
+                coefficient: 0.1,
+                default_tier: "A".to_string(),
+            }],
+            integrations: 2,
+            custom_forms: 12,
+            fit_to_standard: 0.8,
+            legal_entities: 2,
+            countries: 2,
+            languages: 1,
+            profile: Profile {
+                name: "p".to_string(),
+                base_ft: 100.0,
+                basis: 10.0,
+                security_auth: 5.0,
+            },
+            fte: 5.0,
+            utilization: 0.8,
+            overlap_factor: 0.5,
+            schedule: None,
+            formula_version: None,
+        }
+    }
+
+    #[test]
+    fn default_schedule_validates() {
+        assert!(Schedule::default().validate().is_ok());
+    }
+
+    #[test]
+    fn schedule_rejects_phase_weights_not_summing_to_one() {
+        let schedule = Schedule {
+            phase_weights: vec![("Prepare".to_string(), 0.5), ("Explore".to_string(), 0.2)],
+            ..Schedule::default()
+        };
+        let err = schedule.validate().unwrap_err();
+        assert!(err.contains("phase_weights must sum to 1.0"), "{err}");
+    }
+
+    #[test]
+    fn schedule_rejects_non_finite_factor() {
+        let schedule = Schedule {
+            integration_factor: f64::NAN,
+            ..Schedule::default()
+        };
+        let err = schedule.validate().unwrap_err();
+        assert!(err.contains("integration_factor"), "{err}");
+    }
+
+    #[test]
+    fn schedule_rejects_non_positive_working_days() {
+        let schedule = Schedule {
+            working_days_per_month: 0.0,
+            ..Schedule::default()
+        };
+        let err = schedule.validate().unwrap_err();
+        assert!(err.contains("working_days_per_month"), "{err}");
+    }
+
+    #[test]
+    fn custom_schedule_is_threaded_through_calculation() {
+        let mut inputs = sample_inputs();
+        let schedule = Schedule {
+            integration_factor: 10.0,
+            ..Schedule::default()
+        };
+        inputs.schedule = Some(schedule);
+
+        let with_custom = FormulaEngine::compute_scenario(&inputs).unwrap();
+
+        inputs.schedule = None;
+        let with_default = FormulaEngine::compute_scenario(&inputs).unwrap();
+
+        assert!(
+            with_custom.coefficients.sb > with_default.coefficients.sb,
+            "custom schedule's larger integration_factor should raise sb"
+        );
+    }
+
+    #[test]
+    fn closed_form_pmo_matches_iterative_pmo() {
+        let base_effort = 500.0;
+        let capacity = 100.0;
+        let overlap_factor = 0.5;
+
+        let iterative = Schedule {
+            use_iterative_pmo: true,
+            max_pmo_iterations: 1_000,
+            pmo_convergence_threshold: 1e-9,
+            ..Schedule::default()
+        };
+
+        let closed_form =
+            FormulaEngine::solve_pmo(base_effort, capacity, overlap_factor, &Schedule::default(), false).unwrap();
+        let iterated = FormulaEngine::solve_pmo(base_effort, capacity, overlap_factor, &iterative, false).unwrap();
+
+        assert!(
+            (closed_form.0 - iterated.0).abs() < 1e-6,
+            "closed-form d={} should match iterative d={}",
+            closed_form.0,
+            iterated.0
+        );
+        assert!(
+            (closed_form.1 - iterated.1).abs() < 1e-6,
+            "closed-form e_pmo={} should match iterative e_pmo={}",
+            closed_form.1,
+            iterated.1
+        );
+    }
+
+    #[test]
+    fn pmo_divergence_is_rejected() {
+        // rate·overlap/capacity = 10·5/10 = 5 >= 1, so the series diverges.
+        let err = FormulaEngine::solve_pmo(100.0, 10.0, 5.0, &Schedule::default(), false).unwrap_err();
+        assert!(err.contains("does not converge"), "{err}");
+    }
+
+    #[test]
+    fn rejects_non_finite_fte() {
+        let mut inputs = sample_inputs();
+        inputs.fte = f64::NAN;
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("fte"), "{err}");
+    }
+
+    #[test]
+    fn rejects_infinite_overlap_factor() {
+        let mut inputs = sample_inputs();
+        inputs.overlap_factor = f64::INFINITY;
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("overlap_factor"), "{err}");
+    }
+
+    #[test]
+    fn rejects_out_of_range_utilization() {
+        let mut inputs = sample_inputs();
+        inputs.utilization = 1.5;
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("utilization"), "{err}");
+    }
+
+    #[test]
+    fn rejects_non_positive_capacity() {
+        let mut inputs = sample_inputs();
+        inputs.fte = 0.0;
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("Capacity must be positive"), "{err}");
+    }
+
+    #[test]
+    fn scope_breadth_is_clamped_to_max_coefficient() {
+        let mut inputs = sample_inputs();
+        inputs.selected_l3_items = vec![L3ScopeItem {
+            l3_code: "huge".to_string(),
+            coefficient: MAX_COEFFICIENT * 10.0,
+            default_tier: "A".to_string(),
+        }];
+        let results = FormulaEngine::compute_scenario(&inputs).unwrap();
+        assert_eq!(results.coefficients.sb, MAX_COEFFICIENT);
+    }
+
+    #[test]
+    fn batch_reports_per_scenario_failures_without_aborting() {
+        let mut bad = sample_inputs();
+        bad.fte = 0.0;
+        let entries = FormulaEngine::compute_batch_entries(&[sample_inputs(), bad]);
+
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].success);
+        assert!(!entries[1].success);
+        assert!(entries[1].error.as_ref().unwrap().contains("Capacity must be positive"));
+    }
+
+    #[test]
+    fn formula_version_defaults_to_latest() {
+        let inputs = sample_inputs();
+        assert_eq!(inputs.formula_version, None);
+        let results = FormulaEngine::compute_scenario(&inputs).unwrap();
+        assert_eq!(results.formula_version, LATEST_FORMULA_VERSION);
+    }
+
+    #[test]
+    fn formula_version_one_is_explicitly_dispatchable() {
+        let mut inputs = sample_inputs();
+        inputs.formula_version = Some(1);
+        let results = FormulaEngine::compute_scenario(&inputs).unwrap();
+        assert_eq!(results.formula_version, 1);
+    }
+
+    #[test]
+    fn unsupported_formula_version_is_rejected() {
+        let mut inputs = sample_inputs();
+        inputs.formula_version = Some(99);
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("Unsupported formula_version 99"), "{err}");
+    }
+
+    #[test]
+    fn supported_versions_reports_registered_versions() {
+        let engine = FormulaEngine::new();
+        assert_eq!(engine.supported_versions(), SUPPORTED_FORMULA_VERSIONS.to_vec());
+    }
+
+    #[test]
+    fn rejects_negative_fixed_effort_fields() {
+        let mut inputs = sample_inputs();
+        inputs.profile.basis = -1000.0;
+        inputs.profile.security_auth = -1000.0;
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("profile.basis must be non-negative"), "{err}");
+    }
+
+    #[test]
+    fn rejects_negative_base_ft() {
+        let mut inputs = sample_inputs();
+        inputs.profile.base_ft = -1.0;
+        let err = FormulaEngine::compute_scenario(&inputs).unwrap_err();
+        assert!(err.contains("profile.base_ft must be non-negative"), "{err}");
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn calculate_batch_parallel_matches_serial_batch() {
+        let engine = FormulaEngine::new();
+        let mut bad = sample_inputs();
+        bad.fte = 0.0;
+        let inputs_json = serde_json::to_string(&vec![sample_inputs(), bad]).unwrap();
+
+        let parallel: Vec<BatchResultEntry> =
+            serde_json::from_str(&engine.calculate_batch_parallel(&inputs_json).unwrap()).unwrap();
+        let serial: Vec<BatchResultEntry> =
+            serde_json::from_str(&engine.calculate_batch(&inputs_json).unwrap()).unwrap();
+
+        assert_eq!(parallel.len(), serial.len());
+        for (p, s) in parallel.iter().zip(serial.iter()) {
+            assert_eq!(p.index, s.index);
+            assert_eq!(p.success, s.success);
+            assert_eq!(p.error, s.error);
+            assert_eq!(
+                p.result.as_ref().map(|r| r.total_md),
+                s.result.as_ref().map(|r| r.total_md)
+            );
+        }
+    }
+}